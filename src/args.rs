@@ -0,0 +1,241 @@
+//!Macro arguments parsing
+
+use proc_macro::{TokenStream, TokenTree, Delimiter, Span};
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::error::Error;
+
+///Argument's value
+pub struct Value {
+    ///Textual content, without quotes in case of string literal
+    pub text: String,
+    ///Location of the first token within macro input
+    pub span: Span,
+    ///Whether value is given as string literal, which is never considered as flag
+    pub is_literal: bool,
+}
+
+impl Value {
+    #[inline(always)]
+    ///Returns whether value is flag `name`, i.e. identifier rather than string literal
+    pub fn is_flag(&self, name: &str) -> bool {
+        !self.is_literal && self.text == name
+    }
+
+    ///Parses value as `T`, reporting error at value's location
+    pub fn parse<T: FromStr>(&self, expected: &str) -> Result<T, Error> {
+        match self.text.parse() {
+            Ok(value) => Ok(value),
+            Err(_) => Err(self.error(format_args!("expected {expected}, got '{}'", self.text))),
+        }
+    }
+
+    #[inline(always)]
+    ///Creates compile error pointing at value
+    pub fn error(&self, args: fmt::Arguments<'_>) -> Error {
+        Error::at(self.span, args)
+    }
+}
+
+///Macro argument
+pub enum Arg {
+    ///Positional argument, e.g. `master`, `"origin/master"`, `HEAD~3` or flag like `tags`
+    Value(Value),
+    ///Named argument, e.g. `rev = "HEAD~3"` or `short = 8`
+    Named(Value, Value),
+}
+
+impl Arg {
+    #[inline(always)]
+    ///Creates compile error for unexpected argument
+    pub fn unexpected(&self, macro_name: &str) -> Error {
+        match self {
+            Arg::Value(value) => value.error(format_args!("{macro_name}: unexpected argument '{}'", value.text)),
+            Arg::Named(name, _) => name.error(format_args!("{macro_name}: unexpected option '{}'", name.text)),
+        }
+    }
+}
+
+///Parses string literal, returning its content, if token is string literal
+fn unescape(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let raw = &raw[hashes..raw.len() - hashes];
+        return raw.strip_prefix('"').and_then(|raw| raw.strip_suffix('"')).map(str::to_owned);
+    }
+
+    let literal = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            '0' => result.push('\0'),
+            '\\' => result.push('\\'),
+            '\'' => result.push('\''),
+            '"' => result.push('"'),
+            'x' => {
+                let code = chars.as_str().get(..2)?;
+                result.push(u8::from_str_radix(code, 16).ok()? as char);
+                chars.nth(1);
+            },
+            'u' => {
+                let code = chars.as_str().strip_prefix('{')?;
+                let end = code.find('}')?;
+                result.push(char::from_u32(u32::from_str_radix(&code[..end], 16).ok()?)?);
+                chars.nth(end + 1);
+            },
+            //Line continuation skips newline and leading whitespace
+            '\n' => {
+                let rest = chars.as_str().trim_start();
+                chars = rest.chars();
+            },
+            _ => return None,
+        }
+    }
+
+    Some(result)
+}
+
+///Writes tokens without spaces, including content of groups.
+///
+///Adjacent identifiers or literals, which would be separated by space (e.g. `foo bar`), are rejected.
+fn write_tokens(text: &mut String, tokens: impl IntoIterator<Item = TokenTree>) -> Result<(), Error> {
+    let mut is_word = false;
+    for token in tokens {
+        let is_next_word = matches!(token, TokenTree::Ident(_) | TokenTree::Literal(_));
+        if is_word && is_next_word {
+            return Err(Error::at(token.span(), format_args!("unexpected '{token}', use string literal for value with spaces")));
+        }
+        is_word = is_next_word;
+
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                text.push_str(open);
+                write_tokens(text, group.stream())?;
+                text.push_str(close);
+            },
+            token => text.push_str(&token.to_string()),
+        }
+    }
+    Ok(())
+}
+
+///Converts tokens into value.
+///
+///String literal is unescaped, while other tokens are joined without spaces,
+///so that `origin/master`, `HEAD~3` or `HEAD^{commit}` are taken as written.
+fn to_value(tokens: Vec<TokenTree>) -> Result<Value, Error> {
+    let span = tokens[0].span();
+    if let [TokenTree::Literal(literal)] = tokens.as_slice() {
+        let literal = literal.to_string();
+        if let Some(text) = unescape(&literal) {
+            return Ok(Value {
+                text,
+                span,
+                is_literal: true,
+            });
+        }
+    }
+
+    let mut text = String::new();
+    write_tokens(&mut text, tokens)?;
+    Ok(Value {
+        text,
+        span,
+        is_literal: false,
+    })
+}
+
+///Parses comma separated list of arguments
+pub fn parse(input: TokenStream) -> Result<Vec<Arg>, Error> {
+    let mut groups = vec![Vec::new()];
+    for token in input {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                if groups.last().map_or(true, Vec::is_empty) {
+                    return Err(Error::at(punct.span(), format_args!("expected argument before ','")));
+                }
+                groups.push(Vec::new())
+            },
+            token => groups.last_mut().expect("have group").push(token),
+        }
+    }
+    //Allow trailing comma
+    if groups.last().map_or(false, Vec::is_empty) {
+        groups.pop();
+    }
+
+    let mut result = Vec::with_capacity(groups.len());
+    for mut group in groups {
+        let is_named = match group.as_slice() {
+            [TokenTree::Ident(_), TokenTree::Punct(punct), ..] => punct.as_char() == '=',
+            _ => false,
+        };
+
+        if is_named {
+            let mut value = group.split_off(1);
+            let eq = value.remove(0);
+            if value.is_empty() {
+                return Err(Error::at(eq.span(), format_args!("expected value after '='")));
+            }
+            result.push(Arg::Named(to_value(group)?, to_value(value)?));
+        } else {
+            result.push(Arg::Value(to_value(group)?));
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unescape;
+
+    #[test]
+    fn should_unescape_string() {
+        assert_eq!(unescape(r#""origin/master""#).unwrap(), "origin/master");
+        assert_eq!(unescape(r#""""#).unwrap(), "");
+        assert_eq!(unescape(r#""a\"b\\c\'d""#).unwrap(), "a\"b\\c'd");
+        assert_eq!(unescape(r#""\n\r\t\0""#).unwrap(), "\n\r\t\0");
+        assert_eq!(unescape(r#""\x41\x7f""#).unwrap(), "A\x7f");
+        assert_eq!(unescape(r#""\u{48}\u{e9}\u{1F600}!""#).unwrap(), "Hé😀!");
+        assert_eq!(unescape("\"a\\\n    b\"").unwrap(), "ab");
+        assert_eq!(unescape("\"a\\\n\n  \tb\"").unwrap(), "ab");
+    }
+
+    #[test]
+    fn should_unescape_raw_string() {
+        assert_eq!(unescape(r#"r"C:\dir""#).unwrap(), r"C:\dir");
+        assert_eq!(unescape(r###"r#"say "hi""#"###).unwrap(), r#"say "hi""#);
+        assert_eq!(unescape(r####"r##"a"#b"##"####).unwrap(), r##"a"#b"##);
+        assert_eq!(unescape(r#"r"""#).unwrap(), "");
+    }
+
+    #[test]
+    fn should_reject_non_string() {
+        assert!(unescape("42").is_none());
+        assert!(unescape("'a'").is_none());
+        assert!(unescape(r#"b"bytes""#).is_none());
+        assert!(unescape(r#""\q""#).is_none());
+        assert!(unescape(r#""\u{110000}""#).is_none());
+        assert!(unescape(r#""\u{zz}""#).is_none());
+        assert!(unescape(r#""\x4""#).is_none());
+        assert!(unescape(r#""trailing\""#).is_none());
+    }
+}
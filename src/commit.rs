@@ -0,0 +1,296 @@
+//!Commit parsing and date formatting
+
+use core::fmt;
+use core::fmt::Write;
+
+const WEEKDAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"];
+
+///Point in time with timezone offset, as recorded in commit
+#[derive(Clone, Copy)]
+pub struct Time {
+    ///Seconds since Unix epoch
+    pub timestamp: i64,
+    ///Offset from UTC in minutes
+    pub offset: i32,
+}
+
+impl Time {
+    ///Parses `<timestamp> [+-HHMM]`
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split_whitespace();
+        let timestamp = parts.next()?.parse().ok()?;
+        let offset = match parts.next() {
+            Some(offset) => {
+                let (sign, offset) = match (offset.strip_prefix('+'), offset.strip_prefix('-')) {
+                    (Some(offset), _) => (1, offset),
+                    (_, Some(offset)) => (-1, offset),
+                    _ => return None,
+                };
+                if offset.len() != 4 || !offset.bytes().all(|byte| byte.is_ascii_digit()) {
+                    return None;
+                }
+                let hours = offset.get(..2)?.parse::<i32>().ok()?;
+                let minutes = offset.get(2..)?.parse::<i32>().ok()?;
+                sign * (hours * 60 + minutes)
+            },
+            None => 0,
+        };
+
+        match parts.next() {
+            Some(_) => None,
+            None => Some(Self {
+                timestamp,
+                offset,
+            }),
+        }
+    }
+
+    ///Formats time according to `pattern`.
+    ///
+    ///Besides `iso` (ISO 8601) and `rfc2822`, pattern can contain strftime-like specifiers:
+    ///`%Y`, `%y`, `%m`, `%d`, `%e`, `%H`, `%M`, `%S`, `%j`, `%a`, `%A`, `%b`, `%B`, `%z`, `%:z`, `%s`, `%F`, `%T` and `%%`.
+    pub fn format(&self, pattern: &str) -> Result<String, String> {
+        match pattern {
+            "iso" => return self.format("%FT%T%:z"),
+            "rfc2822" => return self.format("%a, %e %b %Y %T %z"),
+            _ => (),
+        }
+
+        let local = match self.timestamp.checked_add(self.offset as i64 * 60) {
+            Some(local) => local,
+            None => return Err(format!("timestamp {} is out of range", self.timestamp)),
+        };
+        let days = local.div_euclid(86400);
+        let seconds = local.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        let weekday = (days + 4).rem_euclid(7) as usize;
+        let year_day = days - days_from_civil(year, 1, 1) + 1;
+        let (hour, minute, second) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        let offset_sign = if self.offset < 0 { '-' } else { '+' };
+        let (offset_hours, offset_minutes) = (self.offset.abs() / 60, self.offset.abs() % 60);
+
+        let mut result = String::new();
+        let mut chars = pattern.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                result.push(ch);
+                continue;
+            }
+
+            let _ = match chars.next() {
+                Some('Y') => write!(result, "{year}"),
+                Some('y') => write!(result, "{:02}", year.rem_euclid(100)),
+                Some('m') => write!(result, "{month:02}"),
+                Some('d') => write!(result, "{day:02}"),
+                Some('e') => write!(result, "{day}"),
+                Some('H') => write!(result, "{hour:02}"),
+                Some('M') => write!(result, "{minute:02}"),
+                Some('S') => write!(result, "{second:02}"),
+                Some('j') => write!(result, "{year_day:03}"),
+                Some('a') => write!(result, "{}", &WEEKDAYS[weekday][..3]),
+                Some('A') => write!(result, "{}", WEEKDAYS[weekday]),
+                Some('b') => write!(result, "{}", &MONTHS[month as usize - 1][..3]),
+                Some('B') => write!(result, "{}", MONTHS[month as usize - 1]),
+                Some('z') => write!(result, "{offset_sign}{offset_hours:02}{offset_minutes:02}"),
+                Some(':') if chars.next() == Some('z') => write!(result, "{offset_sign}{offset_hours:02}:{offset_minutes:02}"),
+                Some('s') => write!(result, "{}", self.timestamp),
+                Some('F') => write!(result, "{year}-{month:02}-{day:02}"),
+                Some('T') => write!(result, "{hour:02}:{minute:02}:{second:02}"),
+                Some('%') => write!(result, "%"),
+                Some(ch) => return Err(format!("unsupported format specifier '%{ch}'")),
+                None => return Err("format ends with '%'".to_owned()),
+            };
+        }
+
+        Ok(result)
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        write!(fmt, "{} {sign}{:02}{:02}", self.timestamp, self.offset.abs() / 60, self.offset.abs() % 60)
+    }
+}
+
+///Converts days since Unix epoch into `(year, month, day)`
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+///Converts date into days since Unix epoch
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+///Commit's author or committer
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    pub time: Time,
+}
+
+impl Signature {
+    ///Parses `<name> <<email>> <timestamp> <offset>`
+    fn parse(text: &str) -> Option<Self> {
+        let (person, time) = text.rsplit_once('>')?;
+        let (name, email) = person.split_once('<')?;
+        Some(Self {
+            name: name.trim().to_owned(),
+            email: email.trim().to_owned(),
+            time: Time::parse(time)?,
+        })
+    }
+}
+
+///Commit's metadata
+pub struct Commit {
+    pub author: Signature,
+    pub committer: Signature,
+    ///Message, as written after headers
+    pub message: String,
+}
+
+impl Commit {
+    ///Parses raw commit object
+    pub fn parse(data: &str) -> Option<Self> {
+        let (headers, message) = data.split_once("\n\n").unwrap_or((data, ""));
+        let mut author = None;
+        let mut committer = None;
+        for line in headers.lines() {
+            if let Some(value) = line.strip_prefix("author ") {
+                author = Signature::parse(value);
+            } else if let Some(value) = line.strip_prefix("committer ") {
+                committer = Signature::parse(value);
+            }
+        }
+
+        Some(Self {
+            author: author?,
+            committer: committer?,
+            message: message.to_owned(),
+        })
+    }
+
+    ///Returns subject, which is first paragraph of message joined into single line, same as `git log --format=%s`
+    pub fn subject(&self) -> String {
+        let message = self.message.trim_start_matches('\n');
+        let paragraph = message.split("\n\n").next().unwrap_or("");
+        paragraph.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ")
+    }
+
+    ///Returns body, which is message after subject
+    pub fn body(&self) -> &str {
+        let message = self.message.trim_start_matches('\n');
+        match message.split_once("\n\n") {
+            Some((_, body)) => body.trim_matches('\n'),
+            None => "",
+        }
+    }
+}
+
+///Removes trailers (e.g. `Signed-off-by: Name <email>`), which are lines of last paragraph in `<token>: <value>` format
+pub fn strip_trailers(text: &str) -> &str {
+    let text = text.trim_end();
+    let (rest, last) = match text.rsplit_once("\n\n") {
+        Some((rest, last)) => (rest, last),
+        None => ("", text),
+    };
+
+    let mut has_trailer = false;
+    for line in last.lines() {
+        //Continuation of multi-line value
+        if line.starts_with(char::is_whitespace) && has_trailer {
+            continue;
+        }
+        match line.split_once(':') {
+            Some((token, _)) if !token.is_empty() && !token.contains(char::is_whitespace) => has_trailer = true,
+            _ => return text,
+        }
+    }
+
+    match has_trailer {
+        true => rest.trim_end(),
+        false => text,
+    }
+}
+
+///Truncates text to at most `len` characters
+pub fn truncate(text: &str, len: usize) -> &str {
+    match text.char_indices().nth(len) {
+        Some((idx, _)) => &text[..idx],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Time;
+
+    fn time(timestamp: i64, offset: i32) -> Time {
+        Time {
+            timestamp,
+            offset,
+        }
+    }
+
+    #[test]
+    fn should_parse_time() {
+        let parsed = Time::parse("1700000000 -0530").unwrap();
+        assert_eq!((parsed.timestamp, parsed.offset), (1700000000, -330));
+        let parsed = Time::parse("-1").unwrap();
+        assert_eq!((parsed.timestamp, parsed.offset), (-1, 0));
+
+        assert!(Time::parse("").is_none());
+        assert!(Time::parse("1 0530").is_none());
+        assert!(Time::parse("1 +053").is_none());
+        assert!(Time::parse("1 +05:30").is_none());
+        assert!(Time::parse("1 +0530 extra").is_none());
+        assert!(Time::parse("1 é").is_none());
+        assert!(Time::parse("1 +0é").is_none());
+        assert!(Time::parse("1 +00é").is_none());
+    }
+
+    #[test]
+    fn should_format_negative_offset() {
+        assert_eq!(time(0, -90).format("iso").unwrap(), "1969-12-31T22:30:00-01:30");
+        assert_eq!(time(0, 330).format("iso").unwrap(), "1970-01-01T05:30:00+05:30");
+        assert_eq!(time(-1, 0).format("%F %T %j %a").unwrap(), "1969-12-31 23:59:59 365 Wed");
+        assert_eq!(time(1700000000, -330).to_string(), "1700000000 -0530");
+    }
+
+    #[test]
+    fn should_format_leap_day() {
+        assert_eq!(time(1709164800, 0).format("rfc2822").unwrap(), "Thu, 29 Feb 2024 00:00:00 +0000");
+        assert_eq!(time(1709164800, 0).format("%j %A %B %e %y").unwrap(), "060 Thursday February 29 24");
+        assert_eq!(time(1735603200, 0).format("%F %j").unwrap(), "2024-12-31 366");
+        assert_eq!(time(951782400, 0).format("%F %j").unwrap(), "2000-02-29 060");
+        assert_eq!(time(1677628800, 0).format("%F %j").unwrap(), "2023-03-01 060");
+    }
+
+    #[test]
+    fn should_reject_invalid_format() {
+        assert_eq!(time(0, 0).format("%s %%").unwrap(), "0 %");
+        assert!(time(0, 0).format("%Q").is_err());
+        assert!(time(0, 0).format("%").is_err());
+        assert!(time(i64::MAX, 60).format("iso").is_err());
+        assert!(time(i64::MIN, -60).format("%s").is_err());
+        assert_eq!(time(i64::MAX, 0).format("%s").unwrap(), i64::MAX.to_string());
+    }
+}
@@ -0,0 +1,77 @@
+//!Macro expansion error
+
+use proc_macro::{TokenStream, TokenTree, Literal, Span, Ident, Punct, Spacing, Group, Delimiter};
+
+use core::fmt;
+
+///Error of macro expansion, reported as `compile_error!`
+#[derive(Clone)]
+pub struct Error {
+    message: String,
+    ///Location within macro input, defaults to whole macro call
+    span: Option<Span>,
+}
+
+impl Error {
+    #[cold]
+    #[inline(never)]
+    ///Creates error pointing at macro call
+    pub fn new(args: fmt::Arguments<'_>) -> Self {
+        Self {
+            message: args.to_string(),
+            span: None,
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    ///Creates error pointing at `span` of macro input
+    pub fn at(span: Span, args: fmt::Arguments<'_>) -> Self {
+        Self {
+            message: args.to_string(),
+            span: Some(span),
+        }
+    }
+
+    ///Points error at `span`, unless it is already more specific
+    pub fn or_span(mut self, span: Option<Span>) -> Self {
+        if self.span.is_none() {
+            self.span = span;
+        }
+        self
+    }
+
+    #[inline(always)]
+    ///Returns error's message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    ///Generates `compile_error!` invocation
+    pub fn to_compile_error(&self) -> TokenStream {
+        let span = self.span.unwrap_or_else(Span::call_site);
+        let mut message = Literal::string(&self.message);
+        message.set_span(span);
+        let mut tokens = vec![
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("core", span)),
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("compile_error", span)),
+            TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Brace, TokenTree::Literal(message).into())),
+        ];
+        for token in tokens.iter_mut() {
+            token.set_span(span);
+        }
+        tokens.into_iter().collect()
+    }
+}
+
+impl From<Error> for TokenStream {
+    #[inline(always)]
+    fn from(error: Error) -> Self {
+        error.to_compile_error()
+    }
+}
@@ -0,0 +1,382 @@
+//!Build information gathered by `git_info`
+
+use proc_macro::{TokenStream, TokenTree, Literal, Delimiter, Span, Punct, Spacing};
+
+use std::env;
+use std::path::Path;
+
+use crate::{Error, Deps, Fallback, Head, Commit, Time};
+
+///Field of build information
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Hash,
+    ShortHash,
+    Branch,
+    Describe,
+    Dirty,
+    CommitTimestamp,
+    AuthorName,
+    AuthorEmail,
+    Root,
+}
+
+impl Field {
+    ///All fields in order of declaration
+    pub const ALL: &'static [Field] = &[
+        Field::Hash,
+        Field::ShortHash,
+        Field::Branch,
+        Field::Describe,
+        Field::Dirty,
+        Field::CommitTimestamp,
+        Field::AuthorName,
+        Field::AuthorEmail,
+        Field::Root,
+    ];
+
+    ///Looks up field by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Field::ALL.iter().copied().find(|field| field.name() == name)
+    }
+
+    ///Returns name of struct field, which is also fallback name
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Hash => "hash",
+            Field::ShortHash => "short_hash",
+            Field::Branch => "branch",
+            Field::Describe => "describe",
+            Field::Dirty => "dirty",
+            Field::CommitTimestamp => "commit_timestamp",
+            Field::AuthorName => "author_name",
+            Field::AuthorEmail => "author_email",
+            Field::Root => "root",
+        }
+    }
+
+    ///Returns type of struct field
+    pub fn ty(self) -> &'static str {
+        match self {
+            Field::Branch => "::core::option::Option<&'static str>",
+            Field::Dirty => "bool",
+            Field::CommitTimestamp => "i64",
+            _ => "&'static str",
+        }
+    }
+
+    ///Returns documentation of struct field
+    pub fn doc(self) -> &'static str {
+        match self {
+            Field::Hash => "Full hash of `HEAD` commit",
+            Field::ShortHash => "Abbreviated hash of `HEAD` commit",
+            Field::Branch => "Current branch, `None` if `HEAD` is detached",
+            Field::Describe => "Output of `git describe --tags --always --dirty`",
+            Field::Dirty => "Whether tracked files have uncommitted changes",
+            Field::CommitTimestamp => "Commit time as Unix timestamp",
+            Field::AuthorName => "Name of commit's author",
+            Field::AuthorEmail => "Email of commit's author",
+            Field::Root => "Path to root of working tree",
+        }
+    }
+}
+
+///Struct to generate, as written by user
+pub struct Definition {
+    ///Attributes of struct
+    pub attrs: TokenStream,
+    ///Visibility of struct, which is used for fields too
+    pub vis: TokenStream,
+    pub name: String,
+    ///Fields with their attributes
+    pub fields: Vec<(TokenStream, Field)>,
+}
+
+impl Definition {
+    ///Parses `[#[attr]] [vis] struct <Name> { [#[attr]] <field>, ... }`
+    pub fn parse(tokens: Vec<TokenTree>) -> Result<Self, Error> {
+        let span = tokens.first().map_or_else(Span::call_site, TokenTree::span);
+        let idx = match tokens.iter().position(|token| matches!(token, TokenTree::Ident(ident) if ident.to_string() == "struct")) {
+            Some(idx) => idx,
+            None => return Err(Error::at(span, format_args!("git_info: expected 'struct <Name> {{ <fields> }}'"))),
+        };
+        let (attrs, vis) = split_attrs(&tokens[..idx]);
+        let name = match tokens.get(idx + 1) {
+            Some(TokenTree::Ident(name)) => name.to_string(),
+            _ => return Err(Error::at(tokens[idx].span(), format_args!("git_info: expected struct name"))),
+        };
+        let body = match tokens.get(idx + 2) {
+            Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace && idx + 3 == tokens.len() => body,
+            _ => return Err(Error::at(tokens[idx].span(), format_args!("git_info: expected fields within braces after struct name"))),
+        };
+
+        let mut fields = Vec::new();
+        let mut field = Vec::new();
+        //Trailing comma terminates the last field
+        let terminator = TokenTree::Punct(Punct::new(',', Spacing::Alone));
+        for token in body.stream().into_iter().chain(core::iter::once(terminator)) {
+            match token {
+                TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                    if field.is_empty() {
+                        continue;
+                    }
+                    let (attrs, name) = split_attrs(&field);
+                    let name = name.into_iter().collect::<Vec<_>>();
+                    match name.as_slice() {
+                        [TokenTree::Ident(ident)] => match Field::from_name(&ident.to_string()) {
+                            Some(info) => fields.push((attrs, info)),
+                            None => return Err(unknown_field("git_info", ident.span(), &ident.to_string())),
+                        },
+                        _ => return Err(Error::at(field[0].span(), format_args!("git_info: expected field name"))),
+                    }
+                    field.clear();
+                },
+                token => field.push(token),
+            }
+        }
+
+        Ok(Self {
+            attrs,
+            vis,
+            name,
+            fields,
+        })
+    }
+}
+
+///Splits leading `#[...]` attributes from the rest of tokens
+fn split_attrs(tokens: &[TokenTree]) -> (TokenStream, TokenStream) {
+    let mut idx = 0;
+    while let (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(group))) = (tokens.get(idx), tokens.get(idx + 1)) {
+        if punct.as_char() != '#' || group.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        idx += 2;
+    }
+    (tokens[..idx].iter().cloned().collect(), tokens[idx..].iter().cloned().collect())
+}
+
+///Creates error for unknown field name
+pub fn unknown_field(macro_name: &str, span: Span, name: &str) -> Error {
+    let names = Field::ALL.iter().map(|field| field.name()).collect::<Vec<_>>().join(", ");
+    Error::at(span, format_args!("{macro_name}: unknown field '{name}', expected one of: {names}"))
+}
+
+///Snapshot of repository, that runs each git command at most once
+pub struct Snapshot<'a> {
+    dir: &'a Path,
+    head: Option<Result<Head, Error>>,
+    branch: Option<Result<Option<String>, Error>>,
+    commit: Option<Result<Commit, Error>>,
+    describe: Option<Result<String, Error>>,
+}
+
+impl<'a> Snapshot<'a> {
+    pub fn new(dir: &'a Path) -> Self {
+        Self {
+            dir,
+            head: None,
+            branch: None,
+            commit: None,
+            describe: None,
+        }
+    }
+
+    fn head(&mut self) -> Result<&Head, Error> {
+        let dir = self.dir;
+        self.head.get_or_insert_with(|| crate::read_head(dir)).as_ref().map_err(Error::clone)
+    }
+
+    fn branch(&mut self) -> Result<&Option<String>, Error> {
+        let dir = self.dir;
+        self.branch.get_or_insert_with(|| crate::current_branch(dir)).as_ref().map_err(Error::clone)
+    }
+
+    fn commit(&mut self) -> Result<&Commit, Error> {
+        let dir = self.dir;
+        self.commit.get_or_insert_with(|| crate::read_commit(dir, "HEAD")).as_ref().map_err(Error::clone)
+    }
+
+    fn describe(&mut self) -> Result<&str, Error> {
+        let dir = self.dir;
+        let describe = self.describe.get_or_insert_with(|| crate::run_git(dir, &["describe", "--tags", "--always", "--dirty"]));
+        match describe {
+            Ok(describe) => Ok(describe.trim()),
+            Err(error) => Err(error.clone()),
+        }
+    }
+
+    ///Retrieves raw value of `field`, in the same format as its fallback value
+    fn value(&mut self, field: Field) -> Result<String, Error> {
+        match field {
+            Field::Hash => Ok(self.head()?.hash.clone()),
+            //Empty name stands for detached `HEAD`
+            Field::Branch => Ok(self.branch()?.clone().unwrap_or_default()),
+            Field::ShortHash => Ok(self.head()?.short_hash.clone()),
+            Field::Root => Ok(self.head()?.root.clone()),
+            Field::Describe => Ok(self.describe()?.to_owned()),
+            Field::Dirty => Ok(self.describe()?.ends_with("-dirty").to_string()),
+            Field::CommitTimestamp => Ok(self.commit()?.committer.time.to_string()),
+            Field::AuthorName => Ok(self.commit()?.author.name.clone()),
+            Field::AuthorEmail => Ok(self.commit()?.author.email.clone()),
+        }
+    }
+
+    ///Resolves `field`, taking fallback into account, and formats it as Rust expression
+    pub fn expr(&mut self, field: Field, warn: bool, deps: &mut Deps) -> Result<String, Error> {
+        if field == Field::CommitTimestamp {
+            deps.envs.push("SOURCE_DATE_EPOCH".to_owned());
+        }
+        let placeholder = match field {
+            Field::Dirty => "false",
+            Field::CommitTimestamp => "0",
+            _ => "",
+        };
+        let fallback = Fallback {
+            name: Some(field.name()),
+            default: None,
+            warn: if warn { Some(placeholder) } else { None },
+        };
+        let value = match (field, env::var("SOURCE_DATE_EPOCH")) {
+            (Field::CommitTimestamp, Ok(epoch)) => epoch,
+            _ => fallback.resolve(self.dir, deps, || self.value(field))?,
+        };
+
+        match field {
+            Field::Branch => match value.is_empty() {
+                true => match crate::ci_branch(deps) {
+                    Some(branch) => Ok(format!("::core::option::Option::Some({})", Literal::string(&branch))),
+                    None => Ok("::core::option::Option::None".to_owned()),
+                },
+                false => Ok(format!("::core::option::Option::Some({})", Literal::string(&value))),
+            },
+            Field::Dirty => match value.parse::<bool>() {
+                Ok(is_dirty) => Ok(is_dirty.to_string()),
+                Err(_) => Err(Error::new(format_args!("git_info: expected bool for '{}', got '{value}'", field.name()))),
+            },
+            Field::CommitTimestamp => match Time::parse(&value) {
+                Some(time) => Ok(format!("{}i64", time.timestamp)),
+                None => Err(Error::new(format_args!("git_info: invalid commit time '{value}', expected '<timestamp> [+-HHMM]'"))),
+            },
+            _ => Ok(Literal::string(&value).to_string()),
+        }
+    }
+}
+
+///Struct deriving `GitInfo`
+pub struct Target {
+    pub name: String,
+    ///Fields with information to fill, if annotated
+    pub fields: Vec<(String, Option<Field>)>,
+    ///Options specified by `#[git(...)]` attribute of struct
+    pub options: TokenStream,
+}
+
+impl Target {
+    ///Parses struct with named fields
+    pub fn parse(input: TokenStream) -> Result<Self, Error> {
+        let tokens = input.into_iter().collect::<Vec<_>>();
+        let idx = match tokens.iter().position(|token| matches!(token, TokenTree::Ident(ident) if ident.to_string() == "struct")) {
+            Some(idx) => idx,
+            None => return Err(Error::new(format_args!("GitInfo: can be derived only for struct"))),
+        };
+
+        let mut options = TokenStream::new();
+        for (_, attr) in attrs(&tokens[..idx]) {
+            if let Some(attr) = attr {
+                if !options.is_empty() {
+                    options.extend(core::iter::once(TokenTree::Punct(Punct::new(',', Spacing::Alone))));
+                }
+                options.extend(attr);
+            }
+        }
+
+        let name = match tokens.get(idx + 1) {
+            Some(TokenTree::Ident(name)) => name.to_string(),
+            _ => return Err(Error::at(tokens[idx].span(), format_args!("GitInfo: expected struct name"))),
+        };
+        let body = match tokens.get(idx + 2) {
+            Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+            Some(token) => return Err(Error::at(token.span(), format_args!("GitInfo: can be derived only for struct with named fields and without generics"))),
+            None => return Err(Error::at(tokens[idx].span(), format_args!("GitInfo: can be derived only for struct with named fields"))),
+        };
+
+        let mut fields = Vec::new();
+        for field in split_fields(body.stream()) {
+            let idx = match field.iter().position(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ':')) {
+                Some(idx) if idx > 0 => idx,
+                _ => return Err(Error::at(field[0].span(), format_args!("GitInfo: expected named field"))),
+            };
+            let name = field[idx - 1].to_string();
+
+            let mut info = None;
+            for (span, attr) in attrs(&field[..idx - 1]) {
+                let attr = match attr {
+                    Some(attr) => attr.into_iter().collect::<Vec<_>>(),
+                    None => continue,
+                };
+                match (attr.as_slice(), info) {
+                    ([TokenTree::Ident(ident)], None) => match Field::from_name(&ident.to_string()) {
+                        Some(field) => info = Some(field),
+                        None => return Err(unknown_field("GitInfo", ident.span(), &ident.to_string())),
+                    },
+                    _ => return Err(Error::at(span, format_args!("GitInfo: expected single #[git(<field>)] attribute"))),
+                }
+            }
+            fields.push((name, info));
+        }
+
+        Ok(Self {
+            name,
+            fields,
+            options,
+        })
+    }
+}
+
+///Iterates over leading `#[...]` attributes, returning content of `#[git(...)]`
+fn attrs(tokens: &[TokenTree]) -> Vec<(Span, Option<TokenStream>)> {
+    let mut result = Vec::new();
+    for pair in tokens.windows(2) {
+        if let [TokenTree::Punct(punct), TokenTree::Group(group)] = pair {
+            if punct.as_char() != '#' || group.delimiter() != Delimiter::Bracket {
+                continue;
+            }
+            let attr = group.stream().into_iter().collect::<Vec<_>>();
+            match attr.as_slice() {
+                [TokenTree::Ident(ident), TokenTree::Group(args)] if ident.to_string() == "git" && args.delimiter() == Delimiter::Parenthesis => {
+                    result.push((ident.span(), Some(args.stream())))
+                },
+                _ => result.push((group.span(), None)),
+            }
+        }
+    }
+    result
+}
+
+///Splits fields by commas, that are not within generic arguments of type
+fn split_fields(body: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut fields = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut is_arrow = false;
+    for token in body {
+        if let TokenTree::Punct(ref punct) = token {
+            match punct.as_char() {
+                ',' if depth == 0 => {
+                    fields.push(Vec::new());
+                    continue;
+                },
+                '<' => depth += 1,
+                //Skip `>` of `->` in function types
+                '>' if !is_arrow => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            is_arrow = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+        } else {
+            is_arrow = false;
+        }
+        fields.last_mut().expect("have field").push(token);
+    }
+    fields.retain(|field| !field.is_empty());
+    fields
+}
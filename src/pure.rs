@@ -0,0 +1,721 @@
+//!Pure Rust access to git repository, without spawning git process
+
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use core::cell::OnceCell;
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::error::Error;
+
+///Minimal length of abbreviated object id, as in git
+const MIN_ABBREV: usize = 7;
+///Maximum number of symbolic refs to follow
+const MAX_SYMREF_DEPTH: usize = 5;
+
+#[cold]
+#[inline(never)]
+fn error(args: fmt::Arguments<'_>) -> Error {
+    Error::new(format_args!("git: {args}"))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut result = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        result.push(DIGITS[(byte >> 4) as usize] as char);
+        result.push(DIGITS[(byte & 0xf) as usize] as char);
+    }
+    result
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len()).step_by(2).map(|idx| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok()).collect()
+}
+
+fn is_hex(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
+///Reads variable length size, used by pack and delta encoding
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        result |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+    }
+}
+
+///Reconstructs object from `base` using `delta` instructions
+fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 0;
+    let _base_size = read_varint(delta, &mut pos)?;
+    let size = read_varint(delta, &mut pos)?;
+
+    let mut result = Vec::with_capacity(size);
+    while let Some(&cmd) = delta.get(pos) {
+        pos += 1;
+        if cmd & 0x80 != 0 {
+            let mut offset = 0usize;
+            let mut len = 0usize;
+            for idx in 0..4 {
+                if cmd & (1 << idx) != 0 {
+                    offset |= (*delta.get(pos)? as usize) << (8 * idx);
+                    pos += 1;
+                }
+            }
+            for idx in 0..3 {
+                if cmd & (0x10 << idx) != 0 {
+                    len |= (*delta.get(pos)? as usize) << (8 * idx);
+                    pos += 1;
+                }
+            }
+            if len == 0 {
+                len = 0x10000;
+            }
+            result.extend_from_slice(base.get(offset..offset + len)?);
+        } else if cmd != 0 {
+            let len = cmd as usize;
+            result.extend_from_slice(delta.get(pos..pos + len)?);
+            pos += len;
+        } else {
+            return None;
+        }
+    }
+
+    match result.len() == size {
+        true => Some(result),
+        false => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+///Type of git object
+pub enum Kind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl Kind {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"commit" => Some(Kind::Commit),
+            b"tree" => Some(Kind::Tree),
+            b"blob" => Some(Kind::Blob),
+            b"tag" => Some(Kind::Tag),
+            _ => None,
+        }
+    }
+
+    fn from_pack(kind: u8) -> Option<Self> {
+        match kind {
+            1 => Some(Kind::Commit),
+            2 => Some(Kind::Tree),
+            3 => Some(Kind::Blob),
+            4 => Some(Kind::Tag),
+            _ => None,
+        }
+    }
+}
+
+///Git object content
+pub struct Object {
+    pub kind: Kind,
+    pub data: Vec<u8>,
+}
+
+impl Object {
+    ///Returns values of header field of commit or tag, which precede message
+    pub fn headers<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let data = core::str::from_utf8(&self.data).unwrap_or("");
+        let headers = match data.find("\n\n") {
+            Some(end) => &data[..end],
+            None => data,
+        };
+        headers.lines().filter_map(move |line| line.strip_prefix(name).and_then(|value| value.strip_prefix(' ')))
+    }
+
+    #[inline(always)]
+    ///Returns value of first header field of commit or tag
+    pub fn header<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        self.headers(name).next()
+    }
+}
+
+///Pack file with its index
+struct Pack {
+    ///Path to `.pack` file
+    path: PathBuf,
+    ///Content of `.idx` file
+    idx: Vec<u8>,
+    ///Number of objects
+    len: usize,
+    ///Length of object id in bytes
+    hash_len: usize,
+}
+
+impl Pack {
+    const IDX_MAGIC: &'static [u8] = b"\xfftOc";
+    const FANOUT_OFFSET: usize = 8;
+    const NAMES_OFFSET: usize = Self::FANOUT_OFFSET + 256 * 4;
+
+    ///Loads index of version 2
+    fn open(idx_path: &Path, hash_len: usize) -> Option<Self> {
+        let idx = fs::read(idx_path).ok()?;
+        if idx.get(..4)? != Self::IDX_MAGIC || idx.get(4..8)? != [0, 0, 0, 2] {
+            return None;
+        }
+        let len = Self::read_u32(&idx, Self::FANOUT_OFFSET + 255 * 4)? as usize;
+        if idx.len() < Self::NAMES_OFFSET + len * (hash_len + 8) {
+            return None;
+        }
+
+        Some(Self {
+            path: idx_path.with_extension("pack"),
+            idx,
+            len,
+            hash_len,
+        })
+    }
+
+    fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+        let bytes = data.get(pos..pos + 4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    ///Returns range of indexes of objects with id starting with `first` byte
+    fn fanout(&self, first: u8) -> (usize, usize) {
+        let end = Self::read_u32(&self.idx, Self::FANOUT_OFFSET + first as usize * 4).unwrap_or(0) as usize;
+        let start = match first {
+            0 => 0,
+            first => Self::read_u32(&self.idx, Self::FANOUT_OFFSET + (first as usize - 1) * 4).unwrap_or(0) as usize,
+        };
+        (start, end.min(self.len))
+    }
+
+    fn name(&self, idx: usize) -> &[u8] {
+        let start = Self::NAMES_OFFSET + idx * self.hash_len;
+        &self.idx[start..start + self.hash_len]
+    }
+
+    fn offset(&self, idx: usize) -> Option<u64> {
+        let offsets = Self::NAMES_OFFSET + self.len * (self.hash_len + 4);
+        let offset = Self::read_u32(&self.idx, offsets + idx * 4)?;
+        if offset & 0x8000_0000 == 0 {
+            return Some(offset as u64);
+        }
+
+        let large_offsets = offsets + self.len * 4;
+        let pos = large_offsets + (offset & 0x7fff_ffff) as usize * 8;
+        let bytes = self.idx.get(pos..pos + 8)?;
+        let mut offset = [0u8; 8];
+        offset.copy_from_slice(bytes);
+        Some(u64::from_be_bytes(offset))
+    }
+
+    ///Looks up offset of object within pack file
+    fn find(&self, id: &[u8]) -> Option<u64> {
+        let (mut start, mut end) = self.fanout(*id.first()?);
+        while start < end {
+            let idx = start + (end - start) / 2;
+            match self.name(idx).cmp(id) {
+                Ordering::Less => start = idx + 1,
+                Ordering::Greater => end = idx,
+                Ordering::Equal => return self.offset(idx),
+            }
+        }
+        None
+    }
+
+    ///Collects ids of objects starting with `prefix`, given as hex
+    fn find_prefix(&self, prefix: &str, result: &mut Vec<String>) {
+        let first = match prefix.get(..2).and_then(|first| u8::from_str_radix(first, 16).ok()) {
+            Some(first) => first,
+            None => return,
+        };
+        let (start, end) = self.fanout(first);
+        for idx in start..end {
+            let name = to_hex(self.name(idx));
+            if name.starts_with(prefix) {
+                result.push(name);
+            }
+        }
+    }
+}
+
+///Git repository
+pub struct Repo {
+    ///Root of working tree, which is git directory itself for bare repository
+    pub work_dir: PathBuf,
+    ///Git directory, containing `HEAD` and `index`
+    pub git_dir: PathBuf,
+    ///Directory with objects and refs, that is shared between worktrees
+    pub common_dir: PathBuf,
+    ///Length of object id in bytes
+    hash_len: usize,
+    ///Pack files, loaded on first access to objects
+    packs: OnceCell<Vec<Pack>>,
+}
+
+impl Repo {
+    ///Discovers repository containing `dir`
+    pub fn open(dir: &Path) -> Result<Self, Error> {
+        let dir = match fs::canonicalize(dir) {
+            Ok(dir) => dir,
+            Err(err) => return Err(error(format_args!("cannot access '{}': {err}", dir.display()))),
+        };
+
+        for work_dir in dir.ancestors() {
+            let dot_git = work_dir.join(".git");
+            let git_dir = if work_dir.join("HEAD").is_file() && work_dir.join("objects").is_dir() && work_dir.join("refs").is_dir() {
+                //Bare repository, which has no working tree
+                work_dir.to_owned()
+            } else if dot_git.is_dir() {
+                dot_git
+            } else if dot_git.is_file() {
+                //Worktree or submodule refers to actual git directory
+                match fs::read_to_string(&dot_git) {
+                    Ok(content) => match content.trim().strip_prefix("gitdir:") {
+                        Some(git_dir) => work_dir.join(git_dir.trim()),
+                        None => continue,
+                    },
+                    Err(_) => continue,
+                }
+            } else {
+                continue;
+            };
+
+            let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+                Ok(common_dir) => git_dir.join(common_dir.trim()),
+                Err(_) => git_dir.clone(),
+            };
+
+            let mut repo = Self {
+                work_dir: work_dir.to_owned(),
+                git_dir,
+                common_dir,
+                hash_len: 20,
+                packs: OnceCell::new(),
+            };
+            repo.hash_len = match repo.config("extensions", "objectformat") {
+                None => 20,
+                Some(format) if format.eq_ignore_ascii_case("sha1") => 20,
+                Some(format) if format.eq_ignore_ascii_case("sha256") => 32,
+                Some(format) => return Err(error(format_args!("unsupported object format '{format}'"))),
+            };
+            return Ok(repo);
+        }
+
+        Err(error(format_args!("not a git repository: '{}'", dir.display())))
+    }
+
+    ///Returns name of hash algorithm, used for object ids
+    pub fn object_format(&self) -> &'static str {
+        match self.hash_len {
+            32 => "sha256",
+            _ => "sha1",
+        }
+    }
+
+    fn packs(&self) -> &[Pack] {
+        self.packs.get_or_init(|| {
+            let mut packs = Vec::new();
+            if let Ok(entries) = fs::read_dir(self.common_dir.join("objects").join("pack")) {
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if path.extension().map_or(false, |extension| extension == "idx") {
+                        packs.extend(Pack::open(&path, self.hash_len));
+                    }
+                }
+            }
+            packs
+        })
+    }
+
+    ///Looks up value of `section.key` within repository config
+    pub fn config(&self, section: &str, key: &str) -> Option<String> {
+        let config = fs::read_to_string(self.common_dir.join("config")).ok()?;
+        let mut is_section = false;
+        let mut result = None;
+        for line in config.lines() {
+            let line = line.trim();
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                is_section = name.trim().eq_ignore_ascii_case(section);
+            } else if is_section {
+                if let Some((name, value)) = line.split_once('=') {
+                    if name.trim().eq_ignore_ascii_case(key) {
+                        //Last value wins
+                        result = Some(value.trim().to_owned());
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    ///Returns path to loose ref file, considering that pseudo refs like `HEAD` belong to worktree
+    pub fn ref_path(&self, name: &str) -> PathBuf {
+        match name.starts_with("refs/") && !name.starts_with("refs/bisect/") && !name.starts_with("refs/worktree/") {
+            true => self.common_dir.join(name),
+            false => self.git_dir.join(name),
+        }
+    }
+
+    fn packed_ref(&self, name: &str) -> Option<String> {
+        let packed_refs = fs::read_to_string(self.common_dir.join("packed-refs")).ok()?;
+        for line in packed_refs.lines() {
+            if line.starts_with('#') || line.starts_with('^') {
+                continue;
+            }
+            match line.split_once(' ') {
+                Some((id, ref_name)) if ref_name == name => return Some(id.to_owned()),
+                _ => continue,
+            }
+        }
+        None
+    }
+
+    ///Reads ref by its full name, following symbolic refs.
+    ///
+    ///Returns full name of final ref and its object id.
+    pub fn read_ref(&self, name: &str) -> Option<(String, String)> {
+        let mut name = name.to_owned();
+        for _ in 0..MAX_SYMREF_DEPTH {
+            let id = match fs::read_to_string(self.ref_path(&name)) {
+                Ok(content) => match content.trim().strip_prefix("ref:") {
+                    Some(target) => {
+                        name = target.trim().to_owned();
+                        continue;
+                    },
+                    None => content.trim().to_owned(),
+                },
+                Err(_) => self.packed_ref(&name)?,
+            };
+            return Some((name, id));
+        }
+        None
+    }
+
+    ///Looks up ref by name as `git rev-parse` does, returning its full name and object id
+    pub fn find_ref(&self, name: &str) -> Option<(String, String)> {
+        let name = match name {
+            "@" => "HEAD",
+            name => name,
+        };
+        let is_pseudo_ref = name.bytes().all(|byte| byte.is_ascii_uppercase() || byte == b'_');
+        if is_pseudo_ref || name.starts_with("refs/") {
+            if let Some(result) = self.read_ref(name) {
+                return Some(result);
+            }
+        }
+
+        let candidates = [
+            format!("refs/{name}"),
+            format!("refs/tags/{name}"),
+            format!("refs/heads/{name}"),
+            format!("refs/remotes/{name}"),
+            format!("refs/remotes/{name}/HEAD"),
+        ];
+        candidates.iter().find_map(|candidate| self.read_ref(candidate))
+    }
+
+    ///Collects ids of all objects starting with `prefix`
+    fn find_objects(&self, prefix: &str) -> Vec<String> {
+        let mut result = Vec::new();
+        if prefix.len() < 2 {
+            return result;
+        }
+
+        let (dir, rest) = prefix.split_at(2);
+        if let Ok(entries) = fs::read_dir(self.common_dir.join("objects").join(dir)) {
+            for entry in entries.flatten() {
+                if let Some(name) = entry.file_name().to_str() {
+                    if name.starts_with(rest) {
+                        result.push(format!("{dir}{name}"));
+                    }
+                }
+            }
+        }
+        for pack in self.packs() {
+            pack.find_prefix(prefix, &mut result);
+        }
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    ///Returns length of abbreviated object id, as configured by `core.abbrev` or estimated from number of objects
+    fn default_abbrev(&self) -> usize {
+        if let Some(abbrev) = self.config("core", "abbrev").and_then(|abbrev| abbrev.parse::<usize>().ok()) {
+            return abbrev;
+        }
+
+        //Same as git: expect collision at square root of number of objects, 4 bits per hex digit
+        let count = self.packs().iter().map(|pack| pack.len).sum::<usize>();
+        let bits = (usize::BITS - count.leading_zeros()) as usize;
+        bits.div_ceil(2).max(MIN_ABBREV)
+    }
+
+    ///Abbreviates object id to at least `len` digits, extending it until it becomes unique.
+    ///
+    ///If `len` is not specified, uses git's default.
+    pub fn abbrev(&self, id: &str, len: Option<usize>) -> String {
+        let mut len = len.unwrap_or_else(|| self.default_abbrev()).clamp(4, id.len());
+        while len < id.len() && self.find_objects(&id[..len]).len() > 1 {
+            len += 1;
+        }
+        id[..len].to_owned()
+    }
+
+    fn read_loose(&self, id: &str) -> Result<Option<Object>, Error> {
+        let path = self.common_dir.join("objects").join(&id[..2]).join(&id[2..]);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(_) => return Ok(None),
+        };
+        let data = match miniz_oxide::inflate::decompress_to_vec_zlib(&data) {
+            Ok(data) => data,
+            Err(err) => return Err(error(format_args!("cannot decompress object {id}: {err:?}"))),
+        };
+
+        let header_end = match data.iter().position(|byte| *byte == 0) {
+            Some(header_end) => header_end,
+            None => return Err(error(format_args!("object {id} has invalid header"))),
+        };
+        let kind = data[..header_end].split(|byte| *byte == b' ').next().and_then(Kind::from_name);
+        match kind {
+            Some(kind) => Ok(Some(Object {
+                kind,
+                data: data[header_end + 1..].to_owned(),
+            })),
+            None => Err(error(format_args!("object {id} has unknown type"))),
+        }
+    }
+
+    ///Decompresses `size` bytes, starting at `pos` of pack file.
+    ///
+    ///Compressed size is unknown, so input is read in growing chunks until decompression succeeds.
+    fn inflate_at(file: &mut File, pos: u64, size: usize) -> Option<Vec<u8>> {
+        let mut window = size as u64 + 64;
+        loop {
+            file.seek(SeekFrom::Start(pos)).ok()?;
+            let mut input = Vec::new();
+            file.by_ref().take(window).read_to_end(&mut input).ok()?;
+            match miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&input, size) {
+                Ok(data) => return Some(data),
+                Err(_) if input.len() as u64 == window => window *= 2,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn read_packed(&self, pack: &Pack, offset: u64) -> Option<Object> {
+        let mut file = File::open(&pack.path).ok()?;
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut header = Vec::new();
+        file.by_ref().take(16 + pack.hash_len as u64).read_to_end(&mut header).ok()?;
+
+        let mut pos = 0;
+        let mut byte = *header.get(pos)?;
+        pos += 1;
+        let kind = (byte >> 4) & 0x7;
+        let mut size = (byte & 0xf) as usize;
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = *header.get(pos)?;
+            pos += 1;
+            size |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+        }
+
+        match kind {
+            //OFS_DELTA, base is within the same pack
+            6 => {
+                let mut byte = *header.get(pos)?;
+                pos += 1;
+                let mut base_offset = (byte & 0x7f) as u64;
+                while byte & 0x80 != 0 {
+                    byte = *header.get(pos)?;
+                    pos += 1;
+                    base_offset = ((base_offset + 1) << 7) | (byte & 0x7f) as u64;
+                }
+                let delta = Self::inflate_at(&mut file, offset + pos as u64, size)?;
+                let base = self.read_packed(pack, offset.checked_sub(base_offset)?)?;
+                Some(Object {
+                    kind: base.kind,
+                    data: apply_delta(&base.data, &delta)?,
+                })
+            },
+            //REF_DELTA, base is referred by id
+            7 => {
+                let base_id = to_hex(header.get(pos..pos + pack.hash_len)?);
+                pos += pack.hash_len;
+                let delta = Self::inflate_at(&mut file, offset + pos as u64, size)?;
+                let base = self.read_object(&base_id).ok()?;
+                Some(Object {
+                    kind: base.kind,
+                    data: apply_delta(&base.data, &delta)?,
+                })
+            },
+            kind => Some(Object {
+                kind: Kind::from_pack(kind)?,
+                data: Self::inflate_at(&mut file, offset + pos as u64, size)?,
+            }),
+        }
+    }
+
+    ///Returns whether `id` is full object id of repository's hash algorithm
+    fn is_object_id(&self, id: &str) -> bool {
+        id.len() == self.hash_len * 2 && is_hex(id)
+    }
+
+    ///Checks that `id`, read from `source`, is full object id
+    fn check_id(&self, id: &str, source: fmt::Arguments<'_>) -> Result<(), Error> {
+        match self.is_object_id(id) {
+            true => Ok(()),
+            false => Err(error(format_args!("{source} contains invalid object id '{id}'"))),
+        }
+    }
+
+    ///Reads object by its full id
+    pub fn read_object(&self, id: &str) -> Result<Object, Error> {
+        if !self.is_object_id(id) {
+            return Err(error(format_args!("invalid object id '{id}'")));
+        }
+        if let Some(object) = self.read_loose(id)? {
+            return Ok(object);
+        }
+
+        if let Some(id_bytes) = from_hex(id) {
+            for pack in self.packs() {
+                if let Some(offset) = pack.find(&id_bytes) {
+                    return match self.read_packed(pack, offset) {
+                        Some(object) => Ok(object),
+                        None => Err(error(format_args!("cannot read object {id} from '{}'", pack.path.display()))),
+                    };
+                }
+            }
+        }
+
+        Err(error(format_args!("object {id} not found")))
+    }
+
+    ///Dereferences tags until object of `kind` is reached, or any non-tag object if `kind` is not specified
+    pub fn peel(&self, id: &str, kind: Option<Kind>) -> Result<(String, Object), Error> {
+        let mut id = id.to_owned();
+        loop {
+            let object = self.read_object(&id)?;
+            let next = match (object.kind, kind) {
+                (object_kind, Some(kind)) if object_kind == kind => return Ok((id, object)),
+                (Kind::Tag, _) => object.header("object"),
+                (Kind::Commit, Some(Kind::Tree)) => object.header("tree"),
+                (_, None) => return Ok((id, object)),
+                (object_kind, Some(kind)) => return Err(error(format_args!("object {id} is {object_kind:?}, not {kind:?}"))),
+            };
+            id = match next {
+                Some(next) => {
+                    self.check_id(next, format_args!("object {id}"))?;
+                    next.to_owned()
+                },
+                None => return Err(error(format_args!("object {id} is malformed"))),
+            };
+        }
+    }
+
+    ///Returns `nth` parent of commit, counting from 1
+    fn parent(&self, id: &str, nth: usize) -> Result<String, Error> {
+        let (id, commit) = self.peel(id, Some(Kind::Commit))?;
+        let parent = commit.headers("parent").nth(nth - 1).map(str::to_owned);
+        match parent {
+            Some(parent) => {
+                self.check_id(&parent, format_args!("commit {id}"))?;
+                Ok(parent)
+            },
+            None => Err(error(format_args!("commit {id} has no parent {nth}"))),
+        }
+    }
+
+    ///Resolves base of revision: ref name or object id
+    fn resolve(&self, name: &str) -> Result<String, Error> {
+        if let Some((ref_name, id)) = self.find_ref(if name.is_empty() { "HEAD" } else { name }) {
+            self.check_id(&id, format_args!("ref '{ref_name}'"))?;
+            return Ok(id);
+        }
+
+        if name.len() >= 4 && is_hex(name) {
+            if name.len() == self.hash_len * 2 {
+                return Ok(name.to_owned());
+            }
+            let mut objects = self.find_objects(name);
+            return match objects.len() {
+                1 => Ok(objects.remove(0)),
+                0 => Err(error(format_args!("unknown revision '{name}'"))),
+                _ => Err(error(format_args!("short object id '{name}' is ambiguous"))),
+            };
+        }
+
+        Err(error(format_args!("unknown revision '{name}'")))
+    }
+
+    ///Resolves revision to object id.
+    ///
+    ///Supports ref names, object ids and `~<n>`, `^<n>`, `^{<type>}` suffixes.
+    pub fn rev_parse(&self, revision: &str) -> Result<String, Error> {
+        let (base, mut suffix) = match revision.find(|ch| ch == '~' || ch == '^') {
+            Some(idx) => revision.split_at(idx),
+            None => (revision, ""),
+        };
+        let mut id = self.resolve(base)?;
+
+        while !suffix.is_empty() {
+            let (op, rest) = suffix.split_at(1);
+            if let Some(rest) = rest.strip_prefix('{').filter(|_| op == "^") {
+                let end = match rest.find('}') {
+                    Some(end) => end,
+                    None => return Err(error(format_args!("invalid revision '{revision}'"))),
+                };
+                let kind = match &rest[..end] {
+                    "" => None,
+                    kind => match Kind::from_name(kind.as_bytes()) {
+                        Some(kind) => Some(kind),
+                        None => return Err(error(format_args!("invalid object type in '{revision}'"))),
+                    },
+                };
+                id = self.peel(&id, kind)?.0;
+                suffix = &rest[end + 1..];
+                continue;
+            }
+
+            let digits = rest.len() - rest.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+            let num = match digits {
+                0 => 1,
+                digits => match rest[..digits].parse::<usize>() {
+                    Ok(num) => num,
+                    Err(_) => return Err(error(format_args!("invalid revision '{revision}'"))),
+                },
+            };
+            suffix = &rest[digits..];
+
+            match (op, num) {
+                ("~", num) => for _ in 0..num {
+                    id = self.parent(&id, 1)?;
+                },
+                (_, 0) => id = self.peel(&id, Some(Kind::Commit))?.0,
+                (_, num) => id = self.parent(&id, num)?,
+            }
+        }
+
+        Ok(id)
+    }
+}
@@ -0,0 +1,147 @@
+//!Semantic version parsing
+
+use core::fmt;
+use core::cmp::Ordering;
+
+///Semantic version, as described by <https://semver.org>
+#[derive(PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    ///Pre-release identifiers, without leading `-`
+    pub pre: String,
+    ///Build metadata, without leading `+`
+    pub build: String,
+}
+
+impl Version {
+    ///Parses `<major>.<minor>.<patch>[-<pre>][+<build>]`
+    pub fn parse(text: &str) -> Option<Self> {
+        //Separator must be followed by identifiers
+        let (text, build) = match text.split_once('+') {
+            Some((_, "")) => return None,
+            Some((text, build)) => (text, build),
+            None => (text, ""),
+        };
+        let (text, pre) = match text.split_once('-') {
+            Some((_, "")) => return None,
+            Some((text, pre)) => (text, pre),
+            None => (text, ""),
+        };
+
+        let mut parts = text.split('.').map(parse_number);
+        let version = Self {
+            major: parts.next()??,
+            minor: parts.next()??,
+            patch: parts.next()??,
+            pre: pre.to_owned(),
+            build: build.to_owned(),
+        };
+        if parts.next().is_some() || !is_valid_identifiers(pre) || !is_valid_identifiers(build) {
+            return None;
+        }
+        if !pre.is_empty() && pre.split('.').any(|part| part.len() > 1 && part.starts_with('0') && part.bytes().all(|byte| byte.is_ascii_digit())) {
+            return None;
+        }
+        Some(version)
+    }
+
+    ///Compares versions by precedence, ignoring build metadata
+    pub fn precedence(&self, other: &Self) -> Ordering {
+        let ordering = (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+
+        //Pre-release has lower precedence than normal version
+        match (self.pre.is_empty(), other.pre.is_empty()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (false, false) => (),
+        }
+
+        let mut left = self.pre.split('.');
+        let mut right = other.pre.split('.');
+        loop {
+            let ordering = match (left.next(), right.next()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                //Numeric identifiers have lower precedence than alphanumeric
+                (Some(left), Some(right)) => match (left.parse::<u64>(), right.parse::<u64>()) {
+                    (Ok(left), Ok(right)) => left.cmp(&right),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => left.cmp(right),
+                },
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            write!(fmt, "-{}", self.pre)?;
+        }
+        if !self.build.is_empty() {
+            write!(fmt, "+{}", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+///Parses numeric part of version, which cannot have leading zeros
+fn parse_number(text: &str) -> Option<u64> {
+    if text.is_empty() || (text.len() > 1 && text.starts_with('0')) || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+///Checks that dot separated identifiers are non-empty and consist of alphanumerics and hyphens
+fn is_valid_identifiers(text: &str) -> bool {
+    text.is_empty() || text.split('.').all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Version;
+    use core::cmp::Ordering;
+
+    #[test]
+    fn should_parse_version() {
+        let version = Version::parse("1.2.3-rc.1+build.5").unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (1, 2, 3));
+        assert_eq!(version.pre, "rc.1");
+        assert_eq!(version.build, "build.5");
+        assert_eq!(version.to_string(), "1.2.3-rc.1+build.5");
+
+        assert!(Version::parse("1.2").is_none());
+        assert!(Version::parse("1.2.3.4").is_none());
+        assert!(Version::parse("01.2.3").is_none());
+        assert!(Version::parse("1.2.3-01").is_none());
+        assert!(Version::parse("1.2.3-").is_none());
+        assert!(Version::parse("1.2.3+a..b").is_none());
+        assert!(Version::parse("v1.2.3").is_none());
+    }
+
+    #[test]
+    fn should_compare_precedence() {
+        let order = ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2", "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0", "1.0.1", "1.1.0", "2.0.0"];
+        for pair in order.windows(2) {
+            let (left, right) = (Version::parse(pair[0]).unwrap(), Version::parse(pair[1]).unwrap());
+            assert_eq!(left.precedence(&right), Ordering::Less, "{} < {}", pair[0], pair[1]);
+            assert_eq!(right.precedence(&left), Ordering::Greater, "{} > {}", pair[1], pair[0]);
+        }
+
+        let build = Version::parse("1.0.0+build").unwrap();
+        assert_eq!(build.precedence(&Version::parse("1.0.0").unwrap()), Ordering::Equal);
+    }
+}